@@ -12,10 +12,74 @@ pub struct Args {
     #[arg(required = true)]
     pub destination: String,
 
+    /// Glob pattern to exclude from a glob `source`; can be given multiple
+    /// times. Has no effect on literal (non-glob) sources
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+
+    /// Copy all SOURCEs into DIR, treating `destination` as an extra source
+    #[arg(short = 't', long, value_name = "DIR")]
+    pub target_directory: Option<String>,
+
+    /// Treat destination as a normal file, never as a directory
+    #[arg(short = 'T', long)]
+    pub no_target_directory: bool,
+
     /// Overwrite destination if it exists
     #[arg(short, long)]
     pub force: bool,
 
+    /// Make a backup of each existing destination file before it is
+    /// overwritten, using CONTROL to select the naming scheme (`none`,
+    /// `simple`, `numbered`, `existing`). Defaults to `existing` when given
+    /// without a value; $VERSION_CONTROL is only consulted by `-b`
+    #[arg(long, value_name = "CONTROL", num_args = 0..=1, default_missing_value = "existing")]
+    pub backup: Option<String>,
+
+    /// Like --backup but does not accept an argument, always consulting
+    /// $VERSION_CONTROL (or `existing` if unset)
+    #[arg(short = 'b')]
+    pub backup_simple: bool,
+
+    /// Override the usual backup suffix (default `~`, or $SIMPLE_BACKUP_SUFFIX)
+    #[arg(long, value_name = "SUFFIX")]
+    pub suffix: Option<String>,
+
+    /// Copy only when the source file is newer than the destination (or
+    /// never overwrite at all). WHEN is `all` (always copy), `older`
+    /// (default when given without a value), or `none` (never overwrite)
+    #[arg(long, value_name = "WHEN", num_args = 0..=1, default_missing_value = "older")]
+    pub update: Option<String>,
+
+    /// Number of worker threads for copying directories in parallel
+    /// (default: the number of CPUs). `--jobs 1` falls back to the
+    /// single-threaded fs_extra-based copy, which copies the whole source
+    /// tree: --max-depth, --respect-gitignore and --atomic do not apply to it
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Hash algorithm for --verify and --update's mtime-tie fallback:
+    /// `blake2s` (default), `blake3`, `sha256`, or `xxh3` (fast,
+    /// non-cryptographic, integrity-only)
+    #[arg(long, value_name = "ALGO")]
+    pub hash: Option<String>,
+
+    /// Preserve the given attributes when copying: a comma list of `mode`,
+    /// `ownership`, `timestamps`, or `all`. Defaults to `all` when given
+    /// without a value
+    #[arg(short = 'p', long, value_name = "ATTR_LIST", num_args = 0..=1, default_missing_value = "all")]
+    pub preserve: Option<String>,
+
+    /// Limit recursive directory copy to this many levels deep (default:
+    /// unbounded)
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Skip files and directories ignored by the nearest `.gitignore` (and
+    /// `.git/info/exclude`), consulting parent directories too
+    #[arg(long)]
+    pub respect_gitignore: bool,
+
     /// Hard link file
     #[arg(long)]
     pub hard_link: bool,
@@ -24,10 +88,22 @@ pub struct Args {
     #[arg(long)]
     pub symlink: bool,
 
+    /// Use a copy-on-write reflink where the filesystem supports it, falling
+    /// back to a regular copy otherwise
+    #[arg(long)]
+    pub reflink: bool,
+
     /// Verify hash of folder / file once copied
     #[arg(long)]
     pub verify: bool,
 
+    /// Write each file to a temp sibling of its destination and rename it
+    /// into place once the copy succeeds, instead of writing the destination
+    /// directly. A single file's --verify check also runs before the rename;
+    /// a directory copy is verified as a whole after every file is in place
+    #[arg(long)]
+    pub atomic: bool,
+
     /// Disable progress bar
     #[arg(long)]
     pub no_progress: bool,
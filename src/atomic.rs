@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use eyre::Result;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+/// Linux/macOS/BSD `EXDEV`: the temp file and `destination` turned out to be
+/// on different filesystems, so the rename below can't be done atomically.
+#[cfg(unix)]
+const EXDEV: i32 = 18;
+
+/// Write to `destination` atomically: `write` streams its payload to a
+/// sibling temp file in the same directory, which is only `rename`d into
+/// place as `destination` once `write` succeeds. On any error the temp file
+/// is removed and `destination` is left untouched. A no-op pass-through to
+/// `write(destination)` when `atomic` is false.
+pub fn write_atomic<F>(destination: &Path, atomic: bool, write: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    if !atomic {
+        return write(destination);
+    }
+
+    let temp = temp_sibling(destination);
+    let result = write(&temp).and_then(|()| commit(&temp, destination));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Build a sibling temp path for `destination`, with a random suffix so
+/// concurrent copies to the same destination don't collide.
+fn temp_sibling(destination: &Path) -> PathBuf {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect();
+    let mut name = destination
+        .file_name()
+        .unwrap_or_else(|| std::ffi::OsStr::new("mc"))
+        .to_os_string();
+    name.push(format!(".{suffix}.tmp"));
+    destination.with_file_name(name)
+}
+
+/// Rename `temp` into place as `destination`, falling back to a copy-and-remove
+/// if they turn out to be on different filesystems (rename is atomic only
+/// within one).
+fn commit(temp: &Path, destination: &Path) -> Result<()> {
+    match std::fs::rename(temp, destination) {
+        Ok(()) => Ok(()),
+        #[cfg(unix)]
+        Err(err) if err.raw_os_error() == Some(EXDEV) => {
+            std::fs::copy(temp, destination)?;
+            std::fs::remove_file(temp)?;
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_atomic_replaces_destination_only_on_success() {
+        let dir = tempdir().unwrap();
+        let destination = dir.path().join("file.txt");
+
+        write_atomic(&destination, true, |path| {
+            fs::write(path, "content")?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "content");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_leaves_destination_untouched_on_failure() {
+        let dir = tempdir().unwrap();
+        let destination = dir.path().join("file.txt");
+        fs::write(&destination, "original").unwrap();
+
+        let result = write_atomic(&destination, true, |path| {
+            fs::write(path, "partial")?;
+            eyre::bail!("simulated failure");
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "original");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_disabled_writes_directly() {
+        let dir = tempdir().unwrap();
+        let destination = dir.path().join("file.txt");
+
+        write_atomic(&destination, false, |path| {
+            assert_eq!(path, destination);
+            fs::write(path, "direct")?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "direct");
+    }
+}
@@ -0,0 +1,178 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use eyre::{bail, Result};
+
+/// Backup naming scheme, mirroring GNU cp's `--backup[=CONTROL]` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupControl {
+    /// Never make a backup.
+    None,
+    /// Always append a fixed suffix (default `~`).
+    Simple,
+    /// Always append a `.~N~` numbered suffix.
+    Numbered,
+    /// Use the numbered form if a numbered backup already exists, simple otherwise.
+    Existing,
+}
+
+impl BackupControl {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "none" | "off" => Ok(Self::None),
+            "simple" | "never" => Ok(Self::Simple),
+            "numbered" | "t" => Ok(Self::Numbered),
+            "existing" | "nil" => Ok(Self::Existing),
+            other => bail!("invalid backup control '{}'", other),
+        }
+    }
+}
+
+/// Resolve the effective backup control from `--backup[=CONTROL]`/`-b`,
+/// falling back to the `VERSION_CONTROL` env var when neither supplies one.
+pub fn resolve_control(backup: &Option<String>, backup_simple: bool) -> Result<BackupControl> {
+    if let Some(control) = backup {
+        return BackupControl::parse(control);
+    }
+    if backup_simple {
+        return match env::var("VERSION_CONTROL") {
+            Ok(control) => BackupControl::parse(&control),
+            Err(_) => Ok(BackupControl::Existing),
+        };
+    }
+    Ok(BackupControl::None)
+}
+
+fn simple_suffix(explicit: &Option<String>) -> String {
+    explicit
+        .clone()
+        .or_else(|| env::var("SIMPLE_BACKUP_SUFFIX").ok())
+        .unwrap_or_else(|| "~".to_string())
+}
+
+fn append(path: &Path, tail: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(tail);
+    PathBuf::from(name)
+}
+
+/// Find the next free `N` for a `.~N~` numbered backup of `path`.
+fn next_numbered(path: &Path) -> Result<u64> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(1);
+    };
+    let prefix = format!("{file_name}.~");
+
+    let mut max_existing = 0;
+    if parent.is_dir() {
+        for entry in std::fs::read_dir(parent)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if let Some(rest) = name.strip_prefix(&prefix) {
+                if let Some(n) = rest.strip_suffix('~').and_then(|n| n.parse::<u64>().ok()) {
+                    max_existing = max_existing.max(n);
+                }
+            }
+        }
+    }
+    Ok(max_existing + 1)
+}
+
+fn backup_name(
+    path: &Path,
+    control: BackupControl,
+    suffix: &Option<String>,
+) -> Result<Option<PathBuf>> {
+    match control {
+        BackupControl::None => Ok(None),
+        BackupControl::Simple => Ok(Some(append(path, &simple_suffix(suffix)))),
+        BackupControl::Numbered => Ok(Some(append(path, &format!(".~{}~", next_numbered(path)?)))),
+        BackupControl::Existing => {
+            let n = next_numbered(path)?;
+            if n > 1 {
+                Ok(Some(append(path, &format!(".~{n}~"))))
+            } else {
+                Ok(Some(append(path, &simple_suffix(suffix))))
+            }
+        }
+    }
+}
+
+/// If `destination` exists, rename it to its backup name before the caller
+/// overwrites it. A no-op when `control` is [`BackupControl::None`] or
+/// `destination` doesn't exist.
+pub fn backup_existing(
+    destination: &Path,
+    control: BackupControl,
+    suffix: &Option<String>,
+) -> Result<()> {
+    if control == BackupControl::None || !destination.exists() {
+        return Ok(());
+    }
+    if let Some(backup) = backup_name(destination, control, suffix)? {
+        tracing::info!(
+            "backing up '{}' to '{}'",
+            destination.display(),
+            backup.display()
+        );
+        std::fs::rename(destination, &backup)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_simple_backup() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        std::fs::write(&target, "original").unwrap();
+
+        backup_existing(&target, BackupControl::Simple, &None).unwrap();
+
+        assert!(!target.exists());
+        assert!(dir.path().join("file.txt~").exists());
+    }
+
+    #[test]
+    fn test_numbered_backup_increments() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+
+        std::fs::write(&target, "v1").unwrap();
+        backup_existing(&target, BackupControl::Numbered, &None).unwrap();
+        assert!(dir.path().join("file.txt.~1~").exists());
+
+        std::fs::write(&target, "v2").unwrap();
+        backup_existing(&target, BackupControl::Numbered, &None).unwrap();
+        assert!(dir.path().join("file.txt.~2~").exists());
+    }
+
+    #[test]
+    fn test_existing_prefers_numbered_when_present() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        std::fs::write(&target, "v1").unwrap();
+        std::fs::write(dir.path().join("file.txt.~1~"), "backup").unwrap();
+
+        backup_existing(&target, BackupControl::Existing, &None).unwrap();
+
+        assert!(dir.path().join("file.txt.~2~").exists());
+        assert!(!dir.path().join("file.txt~").exists());
+    }
+
+    #[test]
+    fn test_none_control_is_noop() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("file.txt");
+        std::fs::write(&target, "original").unwrap();
+
+        backup_existing(&target, BackupControl::None, &None).unwrap();
+
+        assert!(target.exists());
+    }
+}
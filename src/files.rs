@@ -5,7 +5,42 @@ use fs_extra::dir::CopyOptions;
 use fs_extra::dir::{self, TransitState};
 use indicatif::ProgressBar;
 
-use crate::cli::Args;
+use crate::args::Args;
+use crate::atomic;
+use crate::backup;
+use crate::parallel_copy;
+use crate::patterns;
+use crate::path_ops;
+use crate::preserve;
+use crate::progress::create_progress_bar;
+use crate::update;
+use crate::verify;
+use crate::walk;
+
+/// Copy every source in `args.source` into place, looping
+/// [`perform_copy_operation`] over each one with its own progress bar.
+/// Glob patterns in `args.source` are expanded (and `args.exclude`d)
+/// against the filesystem first, then [`path_ops::ensure_valid_paths`]
+/// resolves `-t/-T` and validates the result, so the rest of the pipeline
+/// only ever sees concrete, already-checked source/target pairs.
+pub fn perform_copy(args: &Args) -> Result<()> {
+    let expanded_sources = patterns::expand_sources(&args.source, &args.exclude)?;
+    let (_, targets) = path_ops::ensure_valid_paths(args, &expanded_sources)?;
+
+    for (source, destination_path) in &targets {
+        let source_path = Path::new(source);
+
+        let pb = if args.no_progress {
+            None
+        } else {
+            create_progress_bar()?
+        };
+
+        perform_copy_operation(args, source_path, destination_path, &pb)?;
+    }
+
+    Ok(())
+}
 
 pub fn perform_copy_operation(
     args: &Args,
@@ -13,6 +48,18 @@ pub fn perform_copy_operation(
     destination_path: &Path,
     pb: &Option<ProgressBar>,
 ) -> Result<()> {
+    if !source_path.is_dir() {
+        let update_mode = update::resolve_mode(&args.update)?;
+        let algorithm = verify::resolve_algorithm(&args.hash)?;
+        if !update::should_copy(update_mode, source_path, destination_path, algorithm)? {
+            tracing::info!(
+                "skipping '{}': destination is up to date",
+                source_path.display()
+            );
+            return Ok(());
+        }
+    }
+
     // Perform the copy operation
     if source_path.is_dir() {
         copy_dir(args, source_path, destination_path, pb)?;
@@ -25,12 +72,41 @@ pub fn perform_copy_operation(
     Ok(())
 }
 
-pub fn copy_dir<P: AsRef<Path>>(
+pub fn copy_dir(
+    args: &Args,
+    source_path: &Path,
+    destination_path: &Path,
+    pb: &Option<ProgressBar>,
+) -> Result<()> {
+    // `--jobs 1` keeps the original single-threaded fs_extra walk available
+    // as an explicit fallback; everything else goes through the parallel
+    // copy engine. Each backs up only the individual files it's about to
+    // overwrite, rather than renaming the whole destination tree away up
+    // front.
+    if args.jobs == Some(1) {
+        copy_dir_single_threaded(args, source_path, destination_path, pb)
+    } else {
+        parallel_copy::copy_dir_parallel(args, source_path, destination_path, pb)
+    }
+}
+
+fn copy_dir_single_threaded(
     args: &Args,
-    source_path: P,
-    destination_path: P,
+    source_path: &Path,
+    destination_path: &Path,
     pb: &Option<ProgressBar>,
 ) -> Result<()> {
+    if args.force {
+        let control = backup::resolve_control(&args.backup, args.backup_simple)?;
+        // fs_extra copies the whole tree, so back up everything it may overwrite.
+        for entry in walk::walk(source_path, None, false)? {
+            if entry.kind == walk::EntryKind::File {
+                let target = destination_path.join(&entry.relative);
+                backup::backup_existing(&target, control, &args.suffix)?;
+            }
+        }
+    }
+
     #[allow(unused)]
     let dir_progress_handler = |info: fs_extra::dir::TransitProcess| {
         let progress = info.copied_bytes * 100 / info.total_bytes;
@@ -70,13 +146,26 @@ pub fn copy_dir<P: AsRef<Path>>(
             dir_progress_handler,
         )?;
     };
+
+    let flags = preserve::resolve_preserve(&args.preserve)?;
+    if flags != Default::default() {
+        for entry in walk::walk(source_path, None, false)? {
+            let target = destination_path.join(&entry.relative);
+            preserve::apply(&entry.path, &target, flags, args.verify)?;
+        }
+    }
+
+    if args.verify {
+        let algorithm = verify::resolve_algorithm(&args.hash)?;
+        verify::verify_hash(source_path, destination_path, algorithm)?;
+    }
     Ok(())
 }
 
-pub fn copy_file<P: AsRef<Path>>(
+pub fn copy_file(
     args: &Args,
-    source_path: P,
-    destination_path: P,
+    source_path: &Path,
+    destination_path: &Path,
     pb: &Option<ProgressBar>,
 ) -> Result<()> {
     #[allow(unused)]
@@ -88,11 +177,12 @@ pub fn copy_file<P: AsRef<Path>>(
         };
     };
 
-    if destination_path.as_ref().exists() && !args.force {
-        bail!(
-            "Fail already exists at {}",
-            destination_path.as_ref().display()
-        )
+    if destination_path.exists() && !args.force {
+        bail!("Fail already exists at {}", destination_path.display())
+    }
+    if args.force {
+        let control = backup::resolve_control(&args.backup, args.backup_simple)?;
+        backup::backup_existing(destination_path, control, &args.suffix)?;
     }
     if args.hard_link {
         std::fs::hard_link(source_path, destination_path)?;
@@ -101,22 +191,51 @@ pub fn copy_file<P: AsRef<Path>>(
         std::os::unix::fs::symlink(source_path, destination_path)?;
         #[cfg(windows)]
         std::os::windows::fs::symlink(source_path, destination_path)?;
+    } else if args.verify {
+        // Hash the source in the same pass that streams it to the
+        // destination, instead of re-reading both files afterwards.
+        let algorithm = verify::resolve_algorithm(&args.hash)?;
+        atomic::write_atomic(destination_path, args.atomic, |target| {
+            let source_hash = verify::copy_with_hash(source_path, target, algorithm)?;
+            let dest_hash = verify::hash_file(target, algorithm)?;
+            if source_hash != dest_hash {
+                bail!(
+                    "Hash mismatch ({}) for '{}': expected {}, got {}",
+                    algorithm.name(),
+                    destination_path.display(),
+                    source_hash,
+                    dest_hash
+                );
+            }
+            Ok(())
+        })?;
     } else {
-        let mut file_options = fs_extra::file::CopyOptions::new();
-        file_options.overwrite = args.force;
-        if args.reflink {
-            reflink_copy::reflink_or_copy(source_path, destination_path)?;
-        } else if !args.no_progress {
-            fs_extra::file::copy_with_progress(
-                source_path,
-                destination_path,
-                &file_options,
-                file_progress_handler,
-            )?;
-        } else {
-            fs_extra::file::copy(source_path, destination_path, &file_options)?;
-        }
+        atomic::write_atomic(destination_path, args.atomic, |target| {
+            let mut file_options = fs_extra::file::CopyOptions::new();
+            file_options.overwrite = args.force;
+            if args.reflink {
+                reflink_copy::reflink_or_copy(source_path, target)?;
+            } else if !args.no_progress {
+                fs_extra::file::copy_with_progress(
+                    source_path,
+                    target,
+                    &file_options,
+                    file_progress_handler,
+                )?;
+            } else {
+                fs_extra::file::copy(source_path, target, &file_options)?;
+            }
+            Ok(())
+        })?;
     };
+
+    // Hard links share the source's inode (and thus its metadata already);
+    // symlinks carry no attributes of their own target. Preservation only
+    // makes sense for an actual copy.
+    if !args.hard_link && !args.symlink {
+        let flags = preserve::resolve_preserve(&args.preserve)?;
+        preserve::apply(source_path, destination_path, flags, args.verify)?;
+    }
     Ok(())
 }
 
@@ -134,6 +253,37 @@ mod tests {
         Ok(())
     }
 
+    // Default Args a test can override the fields it cares about on, via
+    // `Args { field: value, ..test_args() }`, instead of restating every
+    // field by hand.
+    fn test_args() -> Args {
+        Args {
+            source: vec![],
+            destination: String::new(),
+            exclude: vec![],
+            target_directory: None,
+            no_target_directory: false,
+            force: false,
+            hard_link: false,
+            symlink: false,
+            reflink: false,
+            preserve: None,
+            max_depth: None,
+            respect_gitignore: false,
+            verify: false,
+            atomic: false,
+            no_progress: true,
+            no_keep_awake: true,
+            keep_display_awake: false,
+            backup: None,
+            backup_simple: false,
+            suffix: None,
+            update: None,
+            jobs: None,
+            hash: None,
+        }
+    }
+
     #[test]
     fn test_copy_file_success() {
         let temp_dir = tempdir().unwrap();
@@ -146,15 +296,9 @@ mod tests {
         // Prepare arguments
         let args = Args {
             force: true,
-            no_progress: true,
-            symlink: false,
-            hard_link: false,
             destination: dest_file.to_str().unwrap().to_string(),
-            keep_display_awake: false,
-            no_keep_awake: true,
             source: vec![source_file.to_str().unwrap().to_string()],
-            verify: false,
-            reflink: false,
+            ..test_args()
         };
 
         // Perform the copy operation
@@ -186,15 +330,9 @@ mod tests {
         // Prepare arguments
         let args = Args {
             force: true,
-            no_progress: true,
-            symlink: false,
-            hard_link: false,
             destination: dest_dir.to_str().unwrap().to_string(),
-            keep_display_awake: false,
-            no_keep_awake: true,
             source: vec![source_dir.to_str().unwrap().to_string()],
-            verify: false,
-            reflink: false,
+            ..test_args()
         };
 
         // Perform the copy operation
@@ -210,4 +348,156 @@ mod tests {
         assert!(dest_dir.exists());
         assert!(dest_dir.join("file.txt").exists());
     }
+
+    #[test]
+    fn test_perform_copy_multiple_sources_into_directory() {
+        let temp_dir = tempdir().unwrap();
+        let source_a = temp_dir.path().join("a.txt");
+        let source_b = temp_dir.path().join("b.txt");
+        let dest_dir = temp_dir.path().join("dest");
+        fs::create_dir(&dest_dir).unwrap();
+        create_test_file(&source_a).unwrap();
+        create_test_file(&source_b).unwrap();
+
+        let args = Args {
+            force: true,
+            destination: dest_dir.to_str().unwrap().to_string(),
+            source: vec![
+                source_a.to_str().unwrap().to_string(),
+                source_b.to_str().unwrap().to_string(),
+            ],
+            ..test_args()
+        };
+
+        perform_copy(&args).unwrap();
+
+        assert!(dest_dir.join("a.txt").exists());
+        assert!(dest_dir.join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_perform_copy_directory_source_requires_force_for_missing_destination() {
+        let temp_dir = tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        let dest_dir = temp_dir.path().join("missing_dest");
+        fs::create_dir_all(&source_dir).unwrap();
+        create_test_file(source_dir.join("file.txt")).unwrap();
+
+        let args = Args {
+            destination: dest_dir.to_str().unwrap().to_string(),
+            source: vec![source_dir.to_str().unwrap().to_string()],
+            ..test_args()
+        };
+
+        assert!(perform_copy(&args).is_err());
+        assert!(!dest_dir.exists());
+    }
+
+    #[test]
+    fn test_perform_copy_multiple_sources_requires_directory_target() {
+        let temp_dir = tempdir().unwrap();
+        let source_a = temp_dir.path().join("a.txt");
+        let source_b = temp_dir.path().join("b.txt");
+        let dest_file = temp_dir.path().join("dest.txt");
+        create_test_file(&source_a).unwrap();
+        create_test_file(&source_b).unwrap();
+
+        let args = Args {
+            force: true,
+            destination: dest_file.to_str().unwrap().to_string(),
+            source: vec![
+                source_a.to_str().unwrap().to_string(),
+                source_b.to_str().unwrap().to_string(),
+            ],
+            ..test_args()
+        };
+
+        assert!(perform_copy(&args).is_err());
+    }
+
+    #[test]
+    fn test_copy_file_backs_up_existing_destination() {
+        let temp_dir = tempdir().unwrap();
+        let source_file = temp_dir.path().join("source.txt");
+        let dest_file = temp_dir.path().join("destination.txt");
+        create_test_file(&source_file).unwrap();
+        fs::write(&dest_file, "old content").unwrap();
+
+        let args = Args {
+            force: true,
+            destination: dest_file.to_str().unwrap().to_string(),
+            source: vec![source_file.to_str().unwrap().to_string()],
+            backup: Some("simple".to_string()),
+            ..test_args()
+        };
+
+        perform_copy_operation(&args, &source_file, &dest_file, &None).unwrap();
+
+        let backup_file = temp_dir.path().join("destination.txt~");
+        assert!(backup_file.exists());
+        assert_eq!(fs::read_to_string(backup_file).unwrap(), "old content");
+        assert_eq!(
+            fs::read_to_string(&dest_file).unwrap(),
+            "Hello, world!\n"
+        );
+    }
+
+    #[test]
+    fn test_copy_dir_backs_up_only_overwritten_files() {
+        let temp_dir = tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        let dest_dir = temp_dir.path().join("destination");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::create_dir_all(&dest_dir).unwrap();
+        create_test_file(source_dir.join("a.txt")).unwrap();
+        create_test_file(source_dir.join("b.txt")).unwrap();
+        fs::write(dest_dir.join("a.txt"), "old a").unwrap();
+
+        let args = Args {
+            force: true,
+            destination: dest_dir.to_str().unwrap().to_string(),
+            source: vec![source_dir.to_str().unwrap().to_string()],
+            backup: Some("simple".to_string()),
+            jobs: Some(1),
+            ..test_args()
+        };
+
+        perform_copy_operation(&args, &source_dir, &dest_dir, &None).unwrap();
+
+        // Only the file that was actually overwritten gets a backup; the
+        // rest of the destination tree (itself, and b.txt which didn't
+        // previously exist) is untouched.
+        assert!(dest_dir.join("a.txt~").exists());
+        assert_eq!(fs::read_to_string(dest_dir.join("a.txt~")).unwrap(), "old a");
+        assert!(!dest_dir.join("b.txt~").exists());
+        assert_eq!(
+            fs::read_to_string(dest_dir.join("a.txt")).unwrap(),
+            "Hello, world!\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dest_dir.join("b.txt")).unwrap(),
+            "Hello, world!\n"
+        );
+    }
+
+    #[test]
+    fn test_update_none_skips_existing_destination() {
+        let temp_dir = tempdir().unwrap();
+        let source_file = temp_dir.path().join("source.txt");
+        let dest_file = temp_dir.path().join("destination.txt");
+        create_test_file(&source_file).unwrap();
+        fs::write(&dest_file, "untouched").unwrap();
+
+        let args = Args {
+            force: true,
+            destination: dest_file.to_str().unwrap().to_string(),
+            source: vec![source_file.to_str().unwrap().to_string()],
+            update: Some("none".to_string()),
+            ..test_args()
+        };
+
+        perform_copy_operation(&args, &source_file, &dest_file, &None).unwrap();
+
+        assert_eq!(fs::read_to_string(&dest_file).unwrap(), "untouched");
+    }
 }
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Caches a built [`Gitignore`] matcher per directory, keyed by that
+/// directory's path, so each `.gitignore` (and `.git/info/exclude`) is
+/// parsed once no matter how many descendants under it get checked.
+#[derive(Default)]
+pub struct IgnoreCache {
+    matchers: HashMap<PathBuf, Gitignore>,
+}
+
+impl IgnoreCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `path` should be pruned from the walk, consulting `path`'s
+    /// own directory and every ancestor up to (and including) `root` for a
+    /// `.gitignore` match, nearest directory first so the most specific
+    /// rule wins.
+    pub fn is_ignored(&mut self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut dir = if is_dir {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        loop {
+            let matched = self.matcher_for(dir).matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+            if dir == root {
+                return false;
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn matcher_for(&mut self, dir: &Path) -> &Gitignore {
+        self.matchers
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| build_matcher(dir))
+    }
+}
+
+fn build_matcher(dir: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    let _ = builder.add(dir.join(".gitignore"));
+    let _ = builder.add(dir.join(".git").join("info").join("exclude"));
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_ignored_matches_direct_gitignore() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+
+        let mut cache = IgnoreCache::new();
+        assert!(cache.is_ignored(dir.path(), &dir.path().join("target"), true));
+        assert!(!cache.is_ignored(dir.path(), &dir.path().join("src"), true));
+    }
+
+    #[test]
+    fn test_is_ignored_consults_parent_gitignore() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::create_dir_all(dir.path().join("nested")).unwrap();
+
+        let mut cache = IgnoreCache::new();
+        let file = dir.path().join("nested").join("debug.log");
+        assert!(cache.is_ignored(dir.path(), &file, false));
+    }
+
+    #[test]
+    fn test_is_ignored_caches_matcher_per_directory() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+
+        let mut cache = IgnoreCache::new();
+        cache.is_ignored(dir.path(), &dir.path().join("a.log"), false);
+        cache.is_ignored(dir.path(), &dir.path().join("b.log"), false);
+
+        assert_eq!(cache.matchers.len(), 1);
+    }
+
+    #[test]
+    fn test_is_ignored_false_without_gitignore() {
+        let dir = tempdir().unwrap();
+
+        let mut cache = IgnoreCache::new();
+        assert!(!cache.is_ignored(dir.path(), &dir.path().join("a.txt"), false));
+    }
+}
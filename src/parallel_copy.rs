@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use eyre::{bail, Result};
+use indicatif::ProgressBar;
+
+use crate::args::Args;
+use crate::atomic;
+use crate::backup;
+use crate::preserve;
+use crate::verify;
+use crate::walk::{self, EntryKind};
+
+/// Recreate `source`'s directory structure under `destination`, then copy
+/// its regular files concurrently across a pool sized to `--jobs` (or
+/// `num_cpus::get()`), aggregating byte counts from every worker into a
+/// single shared `pb` via atomics.
+pub fn copy_dir_parallel(
+    args: &Args,
+    source: &Path,
+    destination: &Path,
+    pb: &Option<ProgressBar>,
+) -> Result<()> {
+    let jobs = args.jobs.unwrap_or_else(num_cpus::get).max(1);
+    let flags = preserve::resolve_preserve(&args.preserve)?;
+
+    std::fs::create_dir_all(destination)?;
+
+    let mut files = Vec::new();
+    // Applied only after every file has been copied, since copying a file
+    // into a directory bumps that directory's mtime.
+    let mut dirs = Vec::new();
+    let mut total_bytes = 0u64;
+
+    for entry in walk::walk(source, args.max_depth, args.respect_gitignore)? {
+        let target = destination.join(&entry.relative);
+
+        match entry.kind {
+            EntryKind::Dir => {
+                std::fs::create_dir_all(&target)?;
+                dirs.push((entry.path, target));
+            }
+            EntryKind::Symlink => copy_symlink_entry(args, &entry.path, &target)?,
+            EntryKind::File => {
+                total_bytes += entry.path.metadata()?.len();
+                files.push((entry.path, target, entry.relative));
+            }
+        }
+    }
+
+    if let Some(pb) = pb {
+        pb.set_length(total_bytes);
+    }
+
+    let copied_bytes = Arc::new(AtomicU64::new(0));
+    // Only populated when `--verify` is set; each worker records the
+    // source hash it computed while streaming that file's copy, so the
+    // whole tree can be verified without re-reading the source afterwards.
+    let source_hashes: Arc<Mutex<Vec<(PathBuf, String)>>> = Arc::new(Mutex::new(Vec::new()));
+    let chunks = split_into_chunks(files, jobs);
+
+    thread::scope(|scope| -> Result<()> {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| {
+                let copied_bytes = Arc::clone(&copied_bytes);
+                let source_hashes = Arc::clone(&source_hashes);
+                let pb = pb.clone();
+                scope.spawn(move || -> Result<()> {
+                    for (src, dst, relative) in chunk {
+                        copy_one_file(args, &src, &dst, &relative, &copied_bytes, &source_hashes, &pb, flags)?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().expect("copy worker thread panicked")?;
+        }
+        Ok(())
+    })?;
+
+    for (src_dir, dst_dir) in &dirs {
+        preserve::apply(src_dir, dst_dir, flags, args.verify)?;
+    }
+
+    if args.verify {
+        let algorithm = verify::resolve_algorithm(&args.hash)?;
+        let source_hashes = source_hashes.lock().unwrap();
+        let combined = verify::combine_hashes(
+            source_hashes.iter().map(|(p, h)| (p.as_path(), h.as_str())),
+            algorithm,
+        );
+        verify::verify_combined_hash(source, &combined, destination, algorithm)?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn copy_one_file(
+    args: &Args,
+    source: &Path,
+    destination: &Path,
+    relative: &Path,
+    copied_bytes: &AtomicU64,
+    source_hashes: &Mutex<Vec<(PathBuf, String)>>,
+    pb: &Option<ProgressBar>,
+    preserve_flags: preserve::PreserveFlags,
+) -> Result<()> {
+    if destination.exists() && !args.force {
+        bail!("Fail already exists at {}", destination.display());
+    }
+    if args.force {
+        let control = backup::resolve_control(&args.backup, args.backup_simple)?;
+        backup::backup_existing(destination, control, &args.suffix)?;
+    }
+
+    let bytes = if args.hard_link {
+        std::fs::hard_link(source, destination)?;
+        std::fs::metadata(source)?.len()
+    } else if args.verify {
+        let algorithm = verify::resolve_algorithm(&args.hash)?;
+        let mut hash = String::new();
+        atomic::write_atomic(destination, args.atomic, |target| {
+            hash = verify::copy_with_hash(source, target, algorithm)?;
+            Ok(())
+        })?;
+        let bytes = std::fs::metadata(destination)?.len();
+        source_hashes.lock().unwrap().push((relative.to_path_buf(), hash));
+        bytes
+    } else {
+        atomic::write_atomic(destination, args.atomic, |target| {
+            std::fs::copy(source, target)?;
+            Ok(())
+        })?;
+        std::fs::metadata(destination)?.len()
+    };
+
+    if !args.hard_link {
+        preserve::apply(source, destination, preserve_flags, args.verify)?;
+    }
+
+    let total = copied_bytes.fetch_add(bytes, Ordering::SeqCst) + bytes;
+    if let Some(pb) = pb {
+        pb.set_position(total);
+    }
+    Ok(())
+}
+
+fn copy_symlink_entry(args: &Args, source: &Path, destination: &Path) -> Result<()> {
+    if destination.exists() && !args.force {
+        bail!("Fail already exists at {}", destination.display());
+    }
+    if args.force {
+        let control = backup::resolve_control(&args.backup, args.backup_simple)?;
+        backup::backup_existing(destination, control, &args.suffix)?;
+    }
+    let link_target = std::fs::read_link(source)?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(link_target, destination)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(link_target, destination)?;
+    Ok(())
+}
+
+type FileEntry = (PathBuf, PathBuf, PathBuf);
+
+fn split_into_chunks(files: Vec<FileEntry>, jobs: usize) -> Vec<Vec<FileEntry>> {
+    let mut chunks: Vec<Vec<FileEntry>> = (0..jobs).map(|_| Vec::new()).collect();
+    for (index, entry) in files.into_iter().enumerate() {
+        chunks[index % jobs].push(entry);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn test_args(force: bool, jobs: Option<usize>) -> Args {
+        test_args_with_verify(force, jobs, false)
+    }
+
+    fn test_args_with_verify(force: bool, jobs: Option<usize>, verify: bool) -> Args {
+        Args {
+            source: vec![],
+            destination: String::new(),
+            exclude: vec![],
+            target_directory: None,
+            no_target_directory: false,
+            force,
+            hard_link: false,
+            symlink: false,
+            reflink: false,
+            preserve: None,
+            max_depth: None,
+            respect_gitignore: false,
+            verify,
+            atomic: false,
+            no_progress: true,
+            no_keep_awake: true,
+            keep_display_awake: false,
+            backup: None,
+            backup_simple: false,
+            suffix: None,
+            update: None,
+            jobs,
+            hash: None,
+        }
+    }
+
+    #[test]
+    fn test_copy_dir_parallel_recreates_tree() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let destination = temp_dir.path().join("destination");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("nested").join("b.txt"), "b").unwrap();
+
+        let args = test_args(true, Some(2));
+        copy_dir_parallel(&args, &source, &destination, &None).unwrap();
+
+        assert_eq!(fs::read_to_string(destination.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            fs::read_to_string(destination.join("nested").join("b.txt")).unwrap(),
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_copy_dir_parallel_respects_force() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let destination = temp_dir.path().join("destination");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&destination).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(destination.join("a.txt"), "existing").unwrap();
+
+        let args = test_args(false, Some(1));
+        assert!(copy_dir_parallel(&args, &source, &destination, &None).is_err());
+    }
+
+    #[test]
+    fn test_copy_dir_parallel_verifies_combined_hash() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        let destination = temp_dir.path().join("destination");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("nested").join("b.txt"), "b").unwrap();
+
+        let args = test_args_with_verify(true, Some(2), true);
+        copy_dir_parallel(&args, &source, &destination, &None).unwrap();
+
+        assert_eq!(fs::read_to_string(destination.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            fs::read_to_string(destination.join("nested").join("b.txt")).unwrap(),
+            "b"
+        );
+    }
+}
@@ -1,42 +1,156 @@
 use eyre::{bail, Result};
 use std::{
     fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 use crate::args::Args;
+use crate::preserve;
 
-pub fn ensure_valid_paths(args: &Args) -> Result<PathBuf> {
-    for source in &args.source {
-        if !Path::new(&source).exists() {
+/// Join `relative` onto `destination_root`, guaranteeing the result stays
+/// under it: a leading `/` is stripped (so an absolute-looking relative
+/// portion can't replace `destination_root` outright), and any `..`
+/// component is rejected outright rather than allowed to climb back above
+/// it.
+///
+/// Today's only caller passes a bare file name (`Path::file_name()`), which
+/// can never contain `/` or `..`, so this is currently just a basename
+/// guard in practice; the traversal checks only start doing real work once
+/// something calls this with a multi-component relative path (e.g. a source
+/// tree copied under `-t`).
+pub fn join_safely(destination_root: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative.trim_start_matches('/'));
+
+    if relative
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        bail!(
+            "'{}' would resolve outside the destination directory",
+            relative.display()
+        );
+    }
+
+    let target = destination_root.join(relative);
+    if !target.starts_with(destination_root) {
+        bail!(
+            "'{}' would resolve outside the destination directory",
+            relative.display()
+        );
+    }
+    Ok(target)
+}
+
+/// Validate `sources`/`args.destination` and resolve where each source ends
+/// up, honoring `-t/--target-directory` and `-T/--no-target-directory`.
+/// `sources` is `args.source` with any glob patterns already expanded by the
+/// caller, so this only ever sees concrete paths. Returns the destination
+/// root and a per-source `(source, target)` mapping computed with
+/// [`join_safely`], so a caller never has to re-derive (and risk getting
+/// wrong) a target path itself.
+pub fn ensure_valid_paths(args: &Args, sources: &[String]) -> Result<(PathBuf, Vec<(String, PathBuf)>)> {
+    if args.target_directory.is_some() && args.no_target_directory {
+        bail!("cannot combine --target-directory with --no-target-directory");
+    }
+
+    for source in sources {
+        if !Path::new(source).exists() {
             bail!("No such source file {}.", source);
         }
     }
 
-    if args.source.len() > 1 && !Path::new(&args.destination).exists() {
-        if args.force {
-            fs::create_dir_all(&args.destination).unwrap();
+    let (sources, destination_path, treat_destination_as_dir) =
+        if let Some(target_directory) = &args.target_directory {
+            let target_dir = PathBuf::from(target_directory);
+            if !target_dir.is_dir() {
+                bail!("target '{}' is not a directory", target_directory);
+            }
+            if !Path::new(&args.destination).exists() {
+                bail!("No such source file {}.", args.destination);
+            }
+            // With -t, the positional `destination` is just another source.
+            let mut sources = sources.to_vec();
+            sources.push(args.destination.clone());
+            (sources, target_dir.canonicalize()?, true)
+        } else if args.no_target_directory {
+            if sources.len() > 1 {
+                bail!(
+                    "extra operand '{}': -T/--no-target-directory requires exactly one source",
+                    sources[1]
+                );
+            }
+            (sources.to_vec(), PathBuf::from(&args.destination), false)
+        } else if sources.len() > 1 {
+            let destination = PathBuf::from(&args.destination);
+            if !destination.is_dir() {
+                bail!("target '{}' is not a directory", args.destination);
+            }
+            (sources.to_vec(), destination.canonicalize()?, true)
         } else {
-            bail!("No such directory {}.", args.destination);
+            // A directory source needs a destination root to land under,
+            // same as the multi-source case above, except here it's
+            // auto-created under --force rather than rejected outright.
+            let source_is_dir = sources.first().is_some_and(|s| Path::new(s).is_dir());
+            if source_is_dir && !Path::new(&args.destination).exists() {
+                if args.force {
+                    fs::create_dir_all(&args.destination).unwrap();
+                } else {
+                    bail!("No such directory {}.", args.destination);
+                }
+            }
+            if args.destination.ends_with('/') && args.force && !Path::new(&args.destination).exists() {
+                fs::create_dir_all(&args.destination).unwrap();
+            }
+            let destination = PathBuf::from(&args.destination);
+            let join_basename = destination.is_dir();
+            let destination = if destination.exists() {
+                destination.canonicalize()?
+            } else {
+                destination
+            };
+            (sources.to_vec(), destination, join_basename)
+        };
+
+    if let Some(parent) = Path::new(&args.destination).parent() {
+        if args.force && !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).unwrap();
         }
     }
 
-    let destination_path = if Path::new(&args.destination).exists() {
-        PathBuf::from(&args.destination).canonicalize().unwrap()
-    } else {
-        if args.destination.ends_with('/') && args.force {
-            fs::create_dir_all(&args.destination).unwrap();
+    if args.atomic {
+        let parent = Path::new(&args.destination)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        if parent.exists() && fs::metadata(parent)?.permissions().readonly() {
+            bail!(
+                "destination parent '{}' is not writable, required by --atomic",
+                parent.display()
+            );
         }
-        PathBuf::from(&args.destination)
-    };
+    }
 
-    if let Some(parent) = Path::new(&args.destination).parent() {
-        if args.force && !parent.exists() {
-            fs::create_dir_all(parent).unwrap();
-        }
+    if args.hard_link && preserve::resolve_preserve(&args.preserve)?.mode {
+        bail!("--preserve=mode cannot be combined with --hard-link: a hard link already shares the source's mode");
     }
 
-    Ok(destination_path)
+    let targets = sources
+        .iter()
+        .map(|source| {
+            let target = if treat_destination_as_dir {
+                let file_name = Path::new(source)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .ok_or_else(|| eyre::eyre!("source '{}' has no file name", source))?;
+                join_safely(&destination_path, file_name)?
+            } else {
+                destination_path.clone()
+            };
+            Ok((source.clone(), target))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((destination_path, targets))
 }
 
 #[cfg(test)]
@@ -45,6 +159,37 @@ mod tests {
     use rand::Rng;
     use tempfile::TempDir;
 
+    // Default Args a test can override the fields it cares about on, via
+    // `Args { field: value, ..test_args() }`, instead of restating every
+    // field by hand.
+    fn test_args() -> Args {
+        Args {
+            source: vec![],
+            destination: String::new(),
+            exclude: vec![],
+            target_directory: None,
+            no_target_directory: false,
+            force: false,
+            hard_link: false,
+            symlink: false,
+            reflink: false,
+            preserve: None,
+            max_depth: None,
+            respect_gitignore: false,
+            verify: false,
+            atomic: false,
+            no_progress: false,
+            no_keep_awake: true,
+            keep_display_awake: false,
+            backup: None,
+            backup_simple: false,
+            suffix: None,
+            update: None,
+            jobs: None,
+            hash: None,
+        }
+    }
+
     #[test]
     fn test_ensure_valid_paths_valid() {
         // Setup a valid directory structure using TempDir
@@ -56,18 +201,14 @@ mod tests {
         let args = Args {
             source: vec![temp_file.to_str().unwrap().to_string()],
             destination: temp_dir.path().to_str().unwrap().to_string(),
-            force: false,
-            no_progress: false,
-            verify: false,
-            symlink: false,
-            hard_link: false,
-            keep_display_awake: false,
-            no_keep_awake: true,
+            ..test_args()
         };
 
-        let result = ensure_valid_paths(&args);
+        let result = ensure_valid_paths(&args, &args.source.clone());
         println!("Result: {:?}", result);
-        assert!(result.is_ok());
+        let (_, targets) = result.unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].1.file_name().unwrap(), "test.txt");
     }
 
     #[test]
@@ -76,15 +217,10 @@ mod tests {
             source: vec!["/non/existent/file.txt".to_string()],
             destination: "/tmp".to_string(),
             force: true,
-            no_progress: false,
-            verify: false,
-            symlink: false,
-            hard_link: false,
-            keep_display_awake: false,
-            no_keep_awake: true,
+            ..test_args()
         };
 
-        let result = ensure_valid_paths(&args);
+        let result = ensure_valid_paths(&args, &args.source.clone());
         println!("Result: {:?}", result);
         assert!(result.is_err());
     }
@@ -112,12 +248,7 @@ mod tests {
             source: vec![temp_file.to_str().unwrap().to_string()],
             destination: destination_dir.clone(),
             force: true,
-            no_progress: false,
-            verify: false,
-            symlink: false,
-            hard_link: false,
-            keep_display_awake: false,
-            no_keep_awake: true,
+            ..test_args()
         };
 
         // Ensure that the destination directory doesn't exist before the test
@@ -128,7 +259,7 @@ mod tests {
         );
 
         // Run the function and check the result
-        let result = ensure_valid_paths(&args);
+        let result = ensure_valid_paths(&args, &args.source.clone());
         println!("Result: {:?}", result);
 
         // Ensure the result is Ok and the destination directory was created
@@ -139,4 +270,34 @@ mod tests {
             destination_dir.display()
         );
     }
+
+    #[test]
+    fn test_join_safely_strips_leading_slash() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = join_safely(temp_dir.path(), "/etc/passwd").unwrap();
+        assert_eq!(target, temp_dir.path().join("etc/passwd"));
+    }
+
+    #[test]
+    fn test_join_safely_rejects_parent_dir_components() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(join_safely(temp_dir.path(), "../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_ensure_valid_paths_rejects_preserve_mode_with_hard_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_file = temp_dir.path().join("test.txt");
+        std::fs::write(&temp_file, b"").unwrap();
+
+        let args = Args {
+            source: vec![temp_file.to_str().unwrap().to_string()],
+            destination: temp_dir.path().to_str().unwrap().to_string(),
+            hard_link: true,
+            preserve: Some("mode".to_string()),
+            ..test_args()
+        };
+
+        assert!(ensure_valid_paths(&args, &args.source.clone()).is_err());
+    }
 }
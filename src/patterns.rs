@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use eyre::{bail, Result};
+
+/// Characters that mark a `source` operand as a glob pattern rather than a
+/// literal path, so a path that happens to contain none of them never pays
+/// for globbing.
+const GLOB_METACHARACTERS: [char; 3] = ['*', '?', '['];
+
+/// A `source` operand, classified up front so literal paths can skip
+/// globbing entirely.
+enum PathOrPattern<'a> {
+    Literal(&'a str),
+    Pattern(&'a str),
+}
+
+impl<'a> PathOrPattern<'a> {
+    fn classify(source: &'a str) -> Self {
+        if source.contains(GLOB_METACHARACTERS) {
+            Self::Pattern(source)
+        } else {
+            Self::Literal(source)
+        }
+    }
+}
+
+/// Whether `source` contains glob metacharacters, i.e. needs to be expanded
+/// against the filesystem rather than checked for existence directly.
+pub fn is_pattern(source: &str) -> bool {
+    matches!(PathOrPattern::classify(source), PathOrPattern::Pattern(_))
+}
+
+/// Expand `sources` into concrete path strings: literal entries pass through
+/// unchanged, glob entries are matched against the filesystem and filtered
+/// against `excludes`. Bails if a pattern matches nothing.
+pub fn expand_sources(sources: &[String], excludes: &[String]) -> Result<Vec<String>> {
+    let exclude_patterns = compile_excludes(excludes)?;
+
+    let mut expanded = Vec::new();
+    for source in sources {
+        match PathOrPattern::classify(source) {
+            PathOrPattern::Literal(path) => expanded.push(path.to_string()),
+            PathOrPattern::Pattern(pattern) => {
+                let matches = glob_matches(pattern, &exclude_patterns)?;
+                if matches.is_empty() {
+                    bail!("pattern '{}' matched no files", pattern);
+                }
+                expanded.extend(matches.into_iter().map(|path| path.display().to_string()));
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Like [`expand_sources`], but only checks whether `pattern` matches
+/// anything (after `excludes`), without returning the matches.
+pub fn has_match(pattern: &str, excludes: &[String]) -> Result<bool> {
+    let exclude_patterns = compile_excludes(excludes)?;
+    Ok(!glob_matches(pattern, &exclude_patterns)?.is_empty())
+}
+
+fn compile_excludes(excludes: &[String]) -> Result<Vec<glob::Pattern>> {
+    excludes
+        .iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern)
+                .map_err(|err| eyre::eyre!("invalid --exclude pattern '{}': {}", pattern, err))
+        })
+        .collect()
+}
+
+fn glob_matches(pattern: &str, exclude_patterns: &[glob::Pattern]) -> Result<Vec<PathBuf>> {
+    let mut matches = Vec::new();
+    for entry in glob::glob(pattern)? {
+        let path = entry?;
+        if !exclude_patterns.iter().any(|exclude| exclude.matches_path(&path)) {
+            matches.push(path);
+        }
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_pattern_detects_metacharacters() {
+        assert!(is_pattern("src/**/*.rs"));
+        assert!(is_pattern("file?.txt"));
+        assert!(is_pattern("[ab].txt"));
+        assert!(!is_pattern("src/main.rs"));
+    }
+
+    #[test]
+    fn test_expand_sources_passes_literals_through() {
+        let expanded = expand_sources(&["a.txt".to_string()], &[]).unwrap();
+        assert_eq!(expanded, vec!["a.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_expand_sources_expands_glob_and_applies_exclude() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "a").unwrap();
+        fs::write(dir.path().join("b.rs"), "b").unwrap();
+
+        let pattern = dir.path().join("*.rs").display().to_string();
+        let exclude = dir.path().join("b.rs").display().to_string();
+
+        let expanded = expand_sources(&[pattern], &[exclude]).unwrap();
+
+        assert_eq!(expanded.len(), 1);
+        assert!(expanded[0].ends_with("a.rs"));
+    }
+
+    #[test]
+    fn test_expand_sources_bails_on_no_match() {
+        let dir = tempdir().unwrap();
+        let pattern = dir.path().join("*.nomatch").display().to_string();
+
+        assert!(expand_sources(&[pattern], &[]).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::Path;
+
+use eyre::Result;
+use filetime::{set_file_times, FileTime};
+
+/// Which metadata attributes to carry over from source to destination,
+/// selected via `-p/--preserve[=ATTR_LIST]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreserveFlags {
+    pub mode: bool,
+    pub ownership: bool,
+    pub timestamps: bool,
+}
+
+impl PreserveFlags {
+    fn any(&self) -> bool {
+        self.mode || self.ownership || self.timestamps
+    }
+}
+
+/// Resolve `-p/--preserve[=ATTR_LIST]` into the attributes to apply. Not
+/// passing `--preserve` at all preserves nothing; a bare `--preserve` (no
+/// value) is equivalent to `all`.
+pub fn resolve_preserve(preserve: &Option<String>) -> Result<PreserveFlags> {
+    let Some(value) = preserve else {
+        return Ok(PreserveFlags::default());
+    };
+
+    let mut flags = PreserveFlags::default();
+    for attr in value.split(',') {
+        match attr {
+            "mode" => flags.mode = true,
+            "ownership" => flags.ownership = true,
+            "timestamps" => flags.timestamps = true,
+            "all" => {
+                flags.mode = true;
+                flags.ownership = true;
+                flags.timestamps = true;
+            }
+            other => eyre::bail!("unknown --preserve attribute '{}'", other),
+        }
+    }
+    Ok(flags)
+}
+
+/// Apply the selected attributes from `source` onto `destination`. Called
+/// once per file after it's written, so it interoperates with the parallel
+/// copy engine by running on each worker as its entry finishes. A failure
+/// to restore metadata is only fatal when `verify` is set (mirroring
+/// `--verify`'s stricter guarantees); otherwise it's logged and the copy
+/// itself still counts as a success.
+pub fn apply(source: &Path, destination: &Path, flags: PreserveFlags, verify: bool) -> Result<()> {
+    if !flags.any() {
+        return Ok(());
+    }
+
+    if let Err(err) = apply_inner(source, destination, flags) {
+        if verify {
+            return Err(err);
+        }
+        tracing::warn!(
+            "failed to preserve attributes on '{}': {}",
+            destination.display(),
+            err
+        );
+    }
+
+    Ok(())
+}
+
+/// `metadata.permissions()` carries the full `st_mode`, so restoring it via
+/// `set_permissions` also restores the executable bit and, where the
+/// platform allows it, the setuid/setgid bits — there's nothing Unix-specific
+/// left to do beyond the plain mode copy below.
+fn apply_inner(source: &Path, destination: &Path, flags: PreserveFlags) -> Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    // Ownership goes first: `chown` clears the setuid/setgid bits, so they
+    // are only kept if the mode is restored after it.
+    #[cfg(unix)]
+    if flags.ownership {
+        use std::os::unix::fs::MetadataExt;
+        std::os::unix::fs::chown(destination, Some(metadata.uid()), Some(metadata.gid()))?;
+    }
+
+    if flags.mode {
+        fs::set_permissions(destination, metadata.permissions())?;
+    }
+
+    if flags.timestamps {
+        let accessed = FileTime::from_last_access_time(&metadata);
+        let modified = FileTime::from_last_modification_time(&metadata);
+        set_file_times(destination, accessed, modified)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_preserve_defaults_to_nothing() {
+        let flags = resolve_preserve(&None).unwrap();
+        assert_eq!(flags, PreserveFlags::default());
+    }
+
+    #[test]
+    fn test_resolve_preserve_all() {
+        let flags = resolve_preserve(&Some("all".to_string())).unwrap();
+        assert!(flags.mode && flags.ownership && flags.timestamps);
+    }
+
+    #[test]
+    fn test_resolve_preserve_comma_list() {
+        let flags = resolve_preserve(&Some("mode,timestamps".to_string())).unwrap();
+        assert!(flags.mode);
+        assert!(!flags.ownership);
+        assert!(flags.timestamps);
+    }
+
+    #[test]
+    fn test_resolve_preserve_rejects_unknown_attribute() {
+        assert!(resolve_preserve(&Some("nope".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_apply_copies_mode_and_timestamps() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "content").unwrap();
+        fs::write(&destination, "content").unwrap();
+
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o741)).unwrap();
+        let mtime = FileTime::from_unix_time(1_600_000_000, 0);
+        set_file_times(&source, mtime, mtime).unwrap();
+
+        apply(
+            &source,
+            &destination,
+            PreserveFlags {
+                mode: true,
+                ownership: false,
+                timestamps: true,
+            },
+            false,
+        )
+        .unwrap();
+
+        let dest_metadata = fs::metadata(&destination).unwrap();
+        assert_eq!(dest_metadata.permissions().mode() & 0o777, 0o741);
+        assert_eq!(
+            FileTime::from_last_modification_time(&dest_metadata),
+            mtime
+        );
+    }
+
+    #[test]
+    fn test_apply_preserves_setuid_bit() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "content").unwrap();
+        fs::write(&destination, "content").unwrap();
+
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o4755)).unwrap();
+
+        apply(
+            &source,
+            &destination,
+            PreserveFlags {
+                mode: true,
+                ownership: false,
+                timestamps: false,
+            },
+            false,
+        )
+        .unwrap();
+
+        let dest_mode = fs::metadata(&destination).unwrap().permissions().mode();
+        assert_eq!(dest_mode & 0o7777, 0o4755);
+    }
+
+    #[test]
+    fn test_apply_warns_but_does_not_fail_when_not_verifying() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "content").unwrap();
+        let destination = dir.path().join("missing.txt");
+
+        let result = apply(
+            &source,
+            &destination,
+            PreserveFlags {
+                mode: true,
+                ownership: false,
+                timestamps: false,
+            },
+            false,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_apply_fails_when_verifying() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "content").unwrap();
+        let destination = dir.path().join("missing.txt");
+
+        let result = apply(
+            &source,
+            &destination,
+            PreserveFlags {
+                mode: true,
+                ownership: false,
+                timestamps: false,
+            },
+            true,
+        );
+        assert!(result.is_err());
+    }
+}
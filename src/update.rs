@@ -0,0 +1,135 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use eyre::{bail, Result};
+
+use crate::verify::{verify_hash, HashAlgorithm};
+
+/// When to copy over an existing destination, mirroring GNU cp's
+/// `--update[=WHEN]` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// Always copy (the default when `--update` isn't given).
+    All,
+    /// Copy only when the source is newer than the destination.
+    Older,
+    /// Never overwrite an existing destination.
+    None,
+}
+
+impl UpdateMode {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "all" => Ok(Self::All),
+            "older" => Ok(Self::Older),
+            "none" => Ok(Self::None),
+            other => bail!("invalid --update mode '{}'", other),
+        }
+    }
+}
+
+/// Resolve the effective update mode from `--update[=WHEN]`. Bare
+/// `--update` arrives as `older` (clap's `default_missing_value`);
+/// omitting the flag entirely defaults to `all`.
+pub fn resolve_mode(update: &Option<String>) -> Result<UpdateMode> {
+    match update {
+        Some(value) => UpdateMode::parse(value),
+        None => Ok(UpdateMode::All),
+    }
+}
+
+/// Decide whether `source` should be copied onto `destination` under
+/// `mode`. When mtimes tie under [`UpdateMode::Older`], falls back to a
+/// content comparison hashed with `algorithm` so clock-skewed syncs don't
+/// skip files that actually differ.
+pub fn should_copy(
+    mode: UpdateMode,
+    source: &Path,
+    destination: &Path,
+    algorithm: HashAlgorithm,
+) -> Result<bool> {
+    if !destination.exists() {
+        return Ok(true);
+    }
+
+    match mode {
+        UpdateMode::All => Ok(true),
+        UpdateMode::None => Ok(false),
+        UpdateMode::Older => {
+            let source_mtime = source.metadata()?.modified()?;
+            let dest_mtime = destination.metadata()?.modified()?;
+            match source_mtime.cmp(&dest_mtime) {
+                Ordering::Greater => Ok(true),
+                Ordering::Less => Ok(false),
+                Ordering::Equal => Ok(verify_hash(source, destination, algorithm).is_err()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_should_copy_when_destination_missing() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "content").unwrap();
+
+        assert!(should_copy(UpdateMode::Older, &source, &destination, HashAlgorithm::Blake2s).unwrap());
+        assert!(should_copy(UpdateMode::None, &source, &destination, HashAlgorithm::Blake2s).unwrap());
+    }
+
+    #[test]
+    fn test_none_never_overwrites_existing() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "new").unwrap();
+        fs::write(&destination, "old").unwrap();
+
+        assert!(!should_copy(UpdateMode::None, &source, &destination, HashAlgorithm::Blake2s).unwrap());
+    }
+
+    #[test]
+    fn test_older_skips_when_source_not_newer() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "content").unwrap();
+        fs::write(&destination, "content").unwrap();
+
+        let now = SystemTime::now();
+        let source_file = fs::File::open(&source).unwrap();
+        source_file
+            .set_modified(now - Duration::from_secs(60))
+            .unwrap();
+        let dest_file = fs::File::open(&destination).unwrap();
+        dest_file.set_modified(now).unwrap();
+
+        assert!(!should_copy(UpdateMode::Older, &source, &destination, HashAlgorithm::Blake2s).unwrap());
+    }
+
+    #[test]
+    fn test_older_falls_back_to_hash_on_mtime_tie() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        let destination = dir.path().join("destination.txt");
+        fs::write(&source, "different content").unwrap();
+        fs::write(&destination, "old content").unwrap();
+
+        let now = SystemTime::now();
+        fs::File::open(&source).unwrap().set_modified(now).unwrap();
+        fs::File::open(&destination)
+            .unwrap()
+            .set_modified(now)
+            .unwrap();
+
+        assert!(should_copy(UpdateMode::Older, &source, &destination, HashAlgorithm::Blake2s).unwrap());
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use blake2::{Blake2s256, Digest};
+use eyre::{bail, Result};
+use sha2::Sha256;
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Hash algorithm used for `--verify` and `--update`'s mtime-tie fallback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// Cryptographic, the default.
+    Blake2s,
+    /// Cryptographic, fast on modern hardware.
+    Blake3,
+    /// Cryptographic, widely interoperable.
+    Sha256,
+    /// Non-cryptographic; maximum throughput for corruption detection only.
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "blake2s" => Ok(Self::Blake2s),
+            "blake3" => Ok(Self::Blake3),
+            "sha256" => Ok(Self::Sha256),
+            "xxh3" => Ok(Self::Xxh3),
+            other => bail!("unknown hash algorithm '{}'", other),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Blake2s => "blake2s",
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+            Self::Xxh3 => "xxh3",
+        }
+    }
+}
+
+/// Resolve the effective hash algorithm from `--hash ALGO`, defaulting to
+/// `blake2s` when not given.
+pub fn resolve_algorithm(hash: &Option<String>) -> Result<HashAlgorithm> {
+    match hash {
+        Some(value) => HashAlgorithm::parse(value),
+        None => Ok(HashAlgorithm::Blake2s),
+    }
+}
+
+/// Small dispatch over the supported digest implementations so the rest of
+/// this module doesn't need to be generic over each one's differing API.
+enum Digester {
+    Blake2s(Blake2s256),
+    Blake3(blake3::Hasher),
+    Sha256(Sha256),
+    Xxh3(Xxh3),
+}
+
+impl Digester {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake2s => Self::Blake2s(Blake2s256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(blake3::Hasher::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Xxh3 => Self::Xxh3(Xxh3::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Blake2s(h) => Digest::update(h, bytes),
+            Self::Blake3(h) => {
+                h.update(bytes);
+            }
+            Self::Sha256(h) => Digest::update(h, bytes),
+            Self::Xxh3(h) => h.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Self::Blake2s(h) => hex::encode(h.finalize()),
+            Self::Blake3(h) => h.finalize().to_hex().to_string(),
+            Self::Sha256(h) => hex::encode(h.finalize()),
+            Self::Xxh3(h) => format!("{:032x}", h.digest128()),
+        }
+    }
+}
+
+/// Wraps a reader, feeding every byte read through a digest as the caller
+/// consumes it. Lets a copy loop hash the source in the same pass it
+/// streams the data to the destination, instead of re-reading it
+/// afterwards just to verify.
+pub struct HashingReader<R> {
+    inner: R,
+    digester: Digester,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R, algorithm: HashAlgorithm) -> Self {
+        Self {
+            inner,
+            digester: Digester::new(algorithm),
+        }
+    }
+
+    pub fn finalize(self) -> String {
+        self.digester.finalize()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.digester.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Hash a single file by reading it once.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let file = File::open(path)?;
+    let mut reader = HashingReader::new(file, algorithm);
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(reader.finalize())
+}
+
+/// Combine per-file `(relative_path, hash)` pairs into a single tree hash,
+/// sorting by path first so the result is independent of copy order
+/// (important now that the parallel engine copies files out of order).
+pub fn combine_hashes<'a>(
+    entries: impl Iterator<Item = (&'a Path, &'a str)>,
+    algorithm: HashAlgorithm,
+) -> String {
+    let mut sorted: Vec<_> = entries.collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut combined = Digester::new(algorithm);
+    for (relative, hash) in sorted {
+        combined.update(relative.to_string_lossy().as_bytes());
+        combined.update(hash.as_bytes());
+    }
+    combined.finalize()
+}
+
+/// Hash every regular file under `path`, combining them into a single tree
+/// hash via [`combine_hashes`].
+pub fn hash_dir(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(path)?.to_path_buf();
+            let hash = hash_file(entry.path(), algorithm)?;
+            entries.push((relative, hash));
+        }
+    }
+    Ok(combine_hashes(
+        entries.iter().map(|(p, h)| (p.as_path(), h.as_str())),
+        algorithm,
+    ))
+}
+
+/// Stream-copy `source` to `destination`, hashing the source bytes as they
+/// pass through the write loop. Returns the source hash, computed in the
+/// same pass as the copy rather than by re-reading the source afterwards.
+pub fn copy_with_hash(source: &Path, destination: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let source_file = File::open(source)?;
+    let mut reader = HashingReader::new(source_file, algorithm);
+    let mut destination_file = File::create(destination)?;
+    io::copy(&mut reader, &mut destination_file)?;
+    Ok(reader.finalize())
+}
+
+/// Verify that `source_path` and `destination_path` hash identically,
+/// reading each once. Bails with the offending path on mismatch.
+pub fn verify_hash(
+    source_path: &Path,
+    destination_path: &Path,
+    algorithm: HashAlgorithm,
+) -> Result<()> {
+    let (source_hash, dst_hash) = if source_path.is_dir() {
+        (
+            hash_dir(source_path, algorithm)?,
+            hash_dir(destination_path, algorithm)?,
+        )
+    } else {
+        (
+            hash_file(source_path, algorithm)?,
+            hash_file(destination_path, algorithm)?,
+        )
+    };
+
+    tracing::info!("Source hash ({}): {}", algorithm.name(), source_hash);
+    tracing::info!("Destination hash ({}): {}", algorithm.name(), dst_hash);
+
+    if source_hash == dst_hash {
+        Ok(())
+    } else {
+        bail!(
+            "Hash mismatch ({}) for '{}': expected {}, got {}",
+            algorithm.name(),
+            destination_path.display(),
+            source_hash,
+            dst_hash
+        )
+    }
+}
+
+/// Compare a source combined from hashes gathered *during* a copy (e.g. by
+/// the parallel copy engine) against a fresh hash of the destination tree,
+/// read back once. Bails with `path` identifying the mismatched copy.
+pub fn verify_combined_hash(
+    path: &Path,
+    source_hash: &str,
+    destination_path: &Path,
+    algorithm: HashAlgorithm,
+) -> Result<()> {
+    let dst_hash = hash_dir(destination_path, algorithm)?;
+    tracing::info!("Source hash ({}): {}", algorithm.name(), source_hash);
+    tracing::info!("Destination hash ({}): {}", algorithm.name(), dst_hash);
+    if source_hash == dst_hash {
+        Ok(())
+    } else {
+        bail!(
+            "Hash mismatch ({}) for '{}': expected {}, got {}",
+            algorithm.name(),
+            path.display(),
+            source_hash,
+            dst_hash
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::{tempdir, NamedTempFile, TempDir};
+
+    #[test]
+    fn test_verify_hash_file_identical() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut source_file = NamedTempFile::new_in(temp_dir.path()).unwrap();
+        let mut destination_file = NamedTempFile::new_in(temp_dir.path()).unwrap();
+
+        let content = b"Hello, world!";
+        source_file.write_all(content).unwrap();
+        destination_file.write_all(content).unwrap();
+
+        let result = verify_hash(source_file.path(), destination_file.path(), HashAlgorithm::Blake2s);
+        assert!(
+            result.is_ok(),
+            "Hashes should be identical for the same content."
+        );
+    }
+
+    #[test]
+    fn test_verify_hash_file_different() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut source_file = NamedTempFile::new_in(temp_dir.path()).unwrap();
+        let mut destination_file = NamedTempFile::new_in(temp_dir.path()).unwrap();
+
+        source_file.write_all(b"Hello, world!").unwrap();
+        destination_file.write_all(b"Goodbye, world!").unwrap();
+
+        let result = verify_hash(source_file.path(), destination_file.path(), HashAlgorithm::Blake2s);
+        assert!(
+            result.is_err(),
+            "Hashes should be different for different content."
+        );
+    }
+
+    #[test]
+    fn test_copy_with_hash_matches_post_hoc_hash() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        let destination = temp_dir.path().join("destination.txt");
+        fs::write(&source, b"stream me").unwrap();
+
+        let streamed_hash = copy_with_hash(&source, &destination, HashAlgorithm::Blake2s).unwrap();
+
+        assert_eq!(
+            streamed_hash,
+            hash_file(&destination, HashAlgorithm::Blake2s).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_combine_hashes_is_order_independent() {
+        let a = (PathBuf::from("a.txt"), "hash-a".to_string());
+        let b = (PathBuf::from("b.txt"), "hash-b".to_string());
+
+        let forward = combine_hashes(
+            [(a.0.as_path(), a.1.as_str()), (b.0.as_path(), b.1.as_str())].into_iter(),
+            HashAlgorithm::Blake2s,
+        );
+        let reversed = combine_hashes(
+            [(b.0.as_path(), b.1.as_str()), (a.0.as_path(), a.1.as_str())].into_iter(),
+            HashAlgorithm::Blake2s,
+        );
+
+        assert_eq!(forward, reversed);
+    }
+
+    #[test]
+    fn test_each_algorithm_round_trips() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source.txt");
+        let destination = temp_dir.path().join("destination.txt");
+        fs::write(&source, b"consistent content").unwrap();
+        fs::copy(&source, &destination).unwrap();
+
+        for algorithm in [
+            HashAlgorithm::Blake2s,
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Xxh3,
+        ] {
+            assert!(verify_hash(&source, &destination, algorithm).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_resolve_algorithm_defaults_to_blake2s() {
+        assert_eq!(resolve_algorithm(&None).unwrap(), HashAlgorithm::Blake2s);
+        assert_eq!(
+            resolve_algorithm(&Some("xxh3".to_string())).unwrap(),
+            HashAlgorithm::Xxh3
+        );
+        assert!(resolve_algorithm(&Some("nope".to_string())).is_err());
+    }
+}
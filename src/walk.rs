@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use eyre::Result;
+use walkdir::WalkDir;
+
+use crate::gitignore::IgnoreCache;
+
+/// Kind of filesystem entry yielded by [`walk`], distinguishing symlinks
+/// from the directories/files they point at so a caller can dispatch on
+/// `--symlink`/`--hard-link` without re-`stat`ing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+/// One entry under a walked source tree. `path` is the absolute source
+/// path; `relative` is its path relative to the walk root.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub relative: PathBuf,
+    pub kind: EntryKind,
+}
+
+/// Walk `root` depth-first, yielding every entry under it (excluding `root`
+/// itself) with directories ordered before their contents, so a caller can
+/// recreate parents before copying into them. `max_depth` bounds how many
+/// path components deep the walk descends (mirrors `--max-depth`; `None`
+/// for unbounded). When `respect_gitignore` is set, directories matched by
+/// a `.gitignore` (or `.git/info/exclude`) are pruned entirely rather than
+/// just filtered out afterwards, so their contents are never even visited.
+pub fn walk(root: &Path, max_depth: Option<usize>, respect_gitignore: bool) -> Result<Vec<Entry>> {
+    let mut walker = WalkDir::new(root).sort_by_file_name();
+    if let Some(max_depth) = max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+
+    let mut ignore_cache = IgnoreCache::new();
+    let mut entries = Vec::new();
+    for entry in walker.into_iter().filter_entry(|entry| {
+        !respect_gitignore || !ignore_cache.is_ignored(root, entry.path(), entry.file_type().is_dir())
+    }) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(root)?.to_path_buf();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let kind = if entry.file_type().is_symlink() {
+            EntryKind::Symlink
+        } else if entry.file_type().is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+
+        entries.push(Entry {
+            path: entry.path().to_path_buf(),
+            relative,
+            kind,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_walk_orders_dirs_before_contents() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested").join("file.txt"), "x").unwrap();
+
+        let entries = walk(dir.path(), None, false).unwrap();
+
+        let dir_index = entries
+            .iter()
+            .position(|e| e.relative == Path::new("nested") && e.kind == EntryKind::Dir)
+            .unwrap();
+        let file_index = entries
+            .iter()
+            .position(|e| e.relative == Path::new("nested/file.txt"))
+            .unwrap();
+        assert!(dir_index < file_index);
+    }
+
+    #[test]
+    fn test_walk_respects_max_depth() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/b/file.txt"), "x").unwrap();
+
+        let entries = walk(dir.path(), Some(1), false).unwrap();
+
+        assert!(entries.iter().any(|e| e.relative == Path::new("a")));
+        assert!(!entries.iter().any(|e| e.relative == Path::new("a/b")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_identifies_symlinks() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("target.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("target.txt"), dir.path().join("link.txt"))
+            .unwrap();
+
+        let entries = walk(dir.path(), None, false).unwrap();
+
+        let link = entries
+            .iter()
+            .find(|e| e.relative == Path::new("link.txt"))
+            .unwrap();
+        assert_eq!(link.kind, EntryKind::Symlink);
+    }
+
+    #[test]
+    fn test_walk_prunes_gitignored_directories() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("target").join("bin"), "x").unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("main.rs"), "x").unwrap();
+
+        let entries = walk(dir.path(), None, true).unwrap();
+
+        assert!(!entries.iter().any(|e| e.relative == Path::new("target")));
+        assert!(entries
+            .iter()
+            .any(|e| e.relative == Path::new("src/main.rs")));
+    }
+}